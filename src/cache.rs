@@ -0,0 +1,67 @@
+//! Content-addressed cache for sanitized chunks.
+//!
+//! Every run used to re-send every chunk to the model, even if a previous run
+//! had already sanitized the exact same text with the exact same template.
+//! [`ChunkCache`] keys the model's response by a hash of `(template_name,
+//! chunk_text)` and stores it as a plain `<hash>.txt` file under a cache
+//! directory, so repeated or interrupted runs can skip chunks they've
+//! already seen instead of duplicating work and output.
+
+use std::fs;
+use std::path::PathBuf;
+
+/// A sidecar store of previously-sanitized chunks, keyed by content hash.
+pub struct ChunkCache {
+    dir: PathBuf,
+    enabled: bool,
+}
+
+impl ChunkCache {
+    /// Create a cache rooted at `dir`. If `enabled` is `false`, every lookup
+    /// misses and every store is a no-op (used to implement `--no-cache`
+    /// without scattering `if` checks through the caller).
+    pub fn new(dir: PathBuf, enabled: bool) -> Self {
+        Self { dir, enabled }
+    }
+
+    /// Compute the cache key for a `(template_name, chunk)` pair.
+    ///
+    /// The key is the hex-encoded BLAKE3 hash of the template name, a `\0`
+    /// separator, and the chunk bytes. The separator keeps a template named
+    /// `"foo"` paired with chunk `"bar"` from colliding with template
+    /// `"foob"` paired with chunk `"ar"`.
+    pub fn key_for(template_name: &str, chunk: &str) -> String {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(template_name.as_bytes());
+        hasher.update(b"\0");
+        hasher.update(chunk.as_bytes());
+        hasher.finalize().to_hex().to_string()
+    }
+
+    /// Look up a previously-cached sanitized chunk by its key.
+    ///
+    /// Returns `None` on a miss, if caching is disabled, or if the cached
+    /// file can't be read.
+    pub fn get(&self, key: &str) -> Option<String> {
+        if !self.enabled {
+            return None;
+        }
+        fs::read_to_string(self.path_for(key)).ok()
+    }
+
+    /// Persist a sanitized chunk under its key.
+    ///
+    /// # Errors
+    /// Returns `Err(String)` if the cache directory or file can't be written.
+    pub fn put(&self, key: &str, sanitized_text: &str) -> Result<(), String> {
+        if !self.enabled {
+            return Ok(());
+        }
+        fs::create_dir_all(&self.dir).map_err(|e| e.to_string())?;
+        fs::write(self.path_for(key), sanitized_text).map_err(|e| e.to_string())
+    }
+
+    fn path_for(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{key}.txt"))
+    }
+}
@@ -0,0 +1,168 @@
+//! Recursive discovery of sanitizable input files.
+//!
+//! `awful_book_sanitizer` used to only look at the top level of `input_dir`,
+//! which meant books organized into chapter subdirectories were silently
+//! skipped. This module walks the whole tree with [`ignore::WalkBuilder`] so
+//! `.gitignore`/`.ignore` rules and hidden-file conventions are respected the
+//! same way they would be by `git` or `ripgrep`, and filters the results down
+//! to a caller-supplied set of file extensions.
+
+use std::path::{Path, PathBuf};
+
+use ignore::WalkBuilder;
+use walkdir::WalkDir;
+
+/// Recursively discover files under `input_dir` whose extension matches one
+/// of `extensions`.
+///
+/// `extensions` entries are compared case-insensitively and without a
+/// leading dot (e.g. `"txt"`, not `".txt"`). Each matching file is returned
+/// at most once, even if it could be reached by more than one extension
+/// filter, and hidden files/directories as well as anything matched by a
+/// `.gitignore` or `.ignore` file are skipped, mirroring `git`'s notion of
+/// "tracked" files.
+///
+/// # Errors
+/// Returns `Err(String)` if `input_dir` cannot be walked (e.g. it doesn't
+/// exist or isn't readable).
+pub fn discover_files(input_dir: &Path, extensions: &[String]) -> Result<Vec<PathBuf>, String> {
+    let wanted: std::collections::HashSet<String> =
+        extensions.iter().map(|ext| ext.to_lowercase()).collect();
+
+    let mut seen = std::collections::HashSet::new();
+    let mut files = Vec::new();
+
+    for result in WalkBuilder::new(input_dir).standard_filters(true).build() {
+        let entry = result.map_err(|e| e.to_string())?;
+        let path = entry.path();
+
+        if !path.is_file() {
+            continue;
+        }
+
+        let matches = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| wanted.contains(&ext.to_lowercase()))
+            .unwrap_or(false);
+
+        if matches && seen.insert(path.to_path_buf()) {
+            files.push(path.to_path_buf());
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Recursively discover `.yaml` files under `output_dir`.
+///
+/// Unlike [`discover_files`], this does **not** honor `.gitignore`/`.ignore`
+/// rules or skip hidden directories: `output_dir` holds *generated* output,
+/// which is routinely gitignored (or itself a hidden path), and applying
+/// `ignore`'s source-discovery filters there would make verification
+/// silently find nothing rather than checking the corpus `process_files`
+/// actually wrote.
+///
+/// # Errors
+/// Returns `Err(String)` if `output_dir` cannot be walked.
+pub fn discover_output_files(output_dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut files = Vec::new();
+
+    for entry in WalkDir::new(output_dir) {
+        let entry = entry.map_err(|e| e.to_string())?;
+        let path = entry.path();
+
+        if path.is_file() && path.extension().and_then(|ext| ext.to_str()) == Some("yaml") {
+            files.push(path.to_path_buf());
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Compute the output path for `input_path`, mirroring its position relative
+/// to `input_dir` underneath `output_dir` and appending `.yaml`.
+///
+/// The mirrored path is relative to `input_dir`, not `input_dir` itself: for
+/// an input dir `book`, the file `book/ch1/page.txt` becomes
+/// `output/ch1/page.txt.yaml`, not `output/book/ch1/page.txt.yaml`. This is
+/// intentional — `input_dir`'s own name is just wherever the caller happened
+/// to point `--input`, and including it would make `output_dir`'s layout
+/// depend on an arbitrary invocation detail rather than purely on the
+/// directory structure *inside* `input_dir`.
+///
+/// # Errors
+/// Returns `Err(String)` if `input_path` is not nested under `input_dir`.
+pub fn mirrored_output_path(
+    input_dir: &Path,
+    output_dir: &Path,
+    input_path: &Path,
+) -> Result<PathBuf, String> {
+    mirrored_path(input_dir, output_dir, input_path, "yaml")
+}
+
+/// Compute the `--diff` review path for `input_path`, mirroring its position
+/// relative to `input_dir` underneath `output_dir` and appending `.diff`.
+///
+/// # Errors
+/// Returns `Err(String)` if `input_path` is not nested under `input_dir`.
+pub fn mirrored_diff_path(
+    input_dir: &Path,
+    output_dir: &Path,
+    input_path: &Path,
+) -> Result<PathBuf, String> {
+    mirrored_path(input_dir, output_dir, input_path, "diff")
+}
+
+/// Reverse [`mirrored_output_path`]: given a `.yaml` file under `output_dir`,
+/// compute the source input file under `input_dir` it was generated from.
+///
+/// # Errors
+/// Returns `Err(String)` if `yaml_path` is not nested under `output_dir`, or
+/// doesn't have a `.yaml` extension.
+pub fn source_path_for_yaml(
+    input_dir: &Path,
+    output_dir: &Path,
+    yaml_path: &Path,
+) -> Result<PathBuf, String> {
+    let relative = yaml_path.strip_prefix(output_dir).map_err(|e| {
+        format!(
+            "{} is not under {}: {e}",
+            yaml_path.display(),
+            output_dir.display()
+        )
+    })?;
+
+    let relative_str = relative.to_string_lossy();
+    let source_relative = relative_str
+        .strip_suffix(".yaml")
+        .ok_or_else(|| format!("{} does not have a .yaml extension", yaml_path.display()))?;
+
+    Ok(input_dir.join(source_relative))
+}
+
+/// Shared implementation backing [`mirrored_output_path`] and
+/// [`mirrored_diff_path`]: mirror `input_path`'s position under `output_dir`
+/// and append `.{extension}` to its file name.
+fn mirrored_path(
+    input_dir: &Path,
+    output_dir: &Path,
+    input_path: &Path,
+    extension: &str,
+) -> Result<PathBuf, String> {
+    let relative = input_path
+        .strip_prefix(input_dir)
+        .map_err(|e| format!("{} is not under {}: {e}", input_path.display(), input_dir.display()))?;
+
+    let mut output_path = output_dir.join(relative);
+    let file_name = output_path
+        .file_name()
+        .ok_or_else(|| format!("{} has no file name", input_path.display()))?
+        .to_string_lossy()
+        .into_owned();
+    output_path.set_file_name(format!("{file_name}.{extension}"));
+
+    Ok(output_path)
+}
@@ -0,0 +1,189 @@
+//! Line-based unified diffs between original and sanitized chunk text.
+//!
+//! OCR sanitization can silently rewrite or drop content, and without a way
+//! to see what the model changed, users have to trust it blindly. This
+//! module computes a classic longest-common-subsequence (LCS) line diff and
+//! renders it as a standard unified diff, so `--diff` mode can show
+//! reviewers exactly what was inserted or removed.
+
+use std::fmt::Write as FmtWrite;
+
+/// Number of unchanged lines to show around each hunk of changes, matching
+/// the conventional `diff -u` default.
+const CONTEXT_LINES: usize = 3;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Tag {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// Render a unified diff between `original` and `sanitized`, split into lines.
+///
+/// `original_label` and `sanitized_label` become the `---`/`+++` file
+/// headers, matching `diff -u old new`. Callers should pass the whole
+/// reassembled file rather than diffing chunk-by-chunk and concatenating the
+/// results: hunk line numbers are counted from the start of `original`/
+/// `sanitized`, so a per-chunk diff would restart numbering at 1 for every
+/// chunk and produce a file `patch` can't apply.
+///
+/// Returns an empty string if the two texts are identical. Otherwise returns
+/// the `---`/`+++` headers followed by one or more `@@ -a,b +c,d @@` hunks
+/// with [`CONTEXT_LINES`] lines of surrounding context, in the same style as
+/// `diff -u`.
+pub fn unified_diff(
+    original_label: &str,
+    original: &str,
+    sanitized_label: &str,
+    sanitized: &str,
+) -> String {
+    let a: Vec<&str> = original.lines().collect();
+    let b: Vec<&str> = sanitized.lines().collect();
+
+    let ops = diff_ops(&a, &b);
+    let hunks = render_hunks(&a, &b, &ops);
+    if hunks.is_empty() {
+        return String::new();
+    }
+
+    format!("--- {original_label}\n+++ {sanitized_label}\n{hunks}")
+}
+
+/// Fill the LCS table from the ends of `a` and `b`, then walk it forward to
+/// produce a sequence of per-line operations describing how to turn `a`
+/// into `b`.
+fn diff_ops(a: &[&str], b: &[&str]) -> Vec<(Tag, usize, usize)> {
+    let (n, m) = (a.len(), b.len());
+
+    // lcs[i][j] = length of the LCS of a[i..] and b[j..]
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    // Walk the table forward, preferring an equal line whenever one is
+    // available so hunks stay as small as possible.
+    let mut ops = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if a[i] == b[j] {
+            ops.push((Tag::Equal, i, j));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            ops.push((Tag::Delete, i, j));
+            i += 1;
+        } else {
+            ops.push((Tag::Insert, i, j));
+            j += 1;
+        }
+    }
+    while i < n {
+        ops.push((Tag::Delete, i, j));
+        i += 1;
+    }
+    while j < m {
+        ops.push((Tag::Insert, i, j));
+        j += 1;
+    }
+
+    ops
+}
+
+/// Group a flat list of per-line operations into unified-diff hunks, each
+/// with up to [`CONTEXT_LINES`] lines of surrounding unchanged context.
+/// Change groups separated by a short run of unchanged lines are merged
+/// into a single hunk rather than printed as adjacent ones.
+fn render_hunks(a: &[&str], b: &[&str], ops: &[(Tag, usize, usize)]) -> String {
+    // Maximal runs of non-`Equal` ops, as `[start, end)` index ranges into `ops`.
+    let mut groups = Vec::new();
+    let mut k = 0;
+    while k < ops.len() {
+        if ops[k].0 == Tag::Equal {
+            k += 1;
+            continue;
+        }
+        let start = k;
+        while k < ops.len() && ops[k].0 != Tag::Equal {
+            k += 1;
+        }
+        groups.push((start, k));
+    }
+
+    // Merge groups separated by <= 2*CONTEXT_LINES unchanged lines, since
+    // their context windows would otherwise overlap.
+    let mut merged: Vec<(usize, usize)> = Vec::new();
+    for (start, end) in groups {
+        match merged.last_mut() {
+            Some(last) if start - last.1 <= CONTEXT_LINES * 2 => last.1 = end,
+            _ => merged.push((start, end)),
+        }
+    }
+
+    let mut out = String::new();
+    for (start, end) in merged {
+        let context_start = start.saturating_sub(CONTEXT_LINES);
+        let context_end = (end + CONTEXT_LINES).min(ops.len());
+        write_hunk(&mut out, a, b, &ops[context_start..context_end]);
+    }
+
+    out
+}
+
+/// Format a single hunk (its `@@` header plus `-`/`+`/context lines).
+fn write_hunk(out: &mut String, a: &[&str], b: &[&str], hunk: &[(Tag, usize, usize)]) {
+    let (mut a_start, mut b_start) = (None, None);
+    let (mut a_count, mut b_count) = (0usize, 0usize);
+
+    for &(tag, i, j) in hunk {
+        match tag {
+            Tag::Equal => {
+                a_start.get_or_insert(i);
+                b_start.get_or_insert(j);
+                a_count += 1;
+                b_count += 1;
+            }
+            Tag::Delete => {
+                a_start.get_or_insert(i);
+                a_count += 1;
+            }
+            Tag::Insert => {
+                b_start.get_or_insert(j);
+                b_count += 1;
+            }
+        }
+    }
+
+    let a_start = a_start.unwrap_or(0);
+    let b_start = b_start.unwrap_or(0);
+
+    // GNU unified diff convention: a side with zero lines (a pure insertion
+    // with no leading context, or a pure deletion with no trailing context)
+    // has no real "start line" to report, so it's written as `0` rather than
+    // the 1-indexed position of a line that doesn't participate in the hunk.
+    let a_line = if a_count == 0 { 0 } else { a_start + 1 };
+    let b_line = if b_count == 0 { 0 } else { b_start + 1 };
+
+    let _ = writeln!(out, "@@ -{a_line},{a_count} +{b_line},{b_count} @@");
+
+    for &(tag, i, j) in hunk {
+        match tag {
+            Tag::Equal => {
+                let _ = writeln!(out, " {}", a[i]);
+            }
+            Tag::Delete => {
+                let _ = writeln!(out, "-{}", a[i]);
+            }
+            Tag::Insert => {
+                let _ = writeln!(out, "+{}", b[j]);
+            }
+        }
+    }
+}
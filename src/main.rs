@@ -1,27 +1,42 @@
 //! # awful_book_sanitizer
 //!
-//! A command-line tool for cleaning up OCR’d book excerpts from `.txt` files.
+//! A command-line tool for cleaning up OCR’d book excerpts from text files.
 //!
 //! Many scanned books contain corrupted characters, misspelled words, and poor grammar.
-//! This tool reads plain text files, splits them into 500-token chunks, and asks a
-//! Large Language Model (LLM) (via an OpenAI-compatible endpoint) to **sanitize** them.
+//! This tool recursively crawls an input directory for files matching a configurable
+//! set of extensions (`--extensions txt,md,ocr`, honoring `.gitignore`/`.ignore` rules
+//! along the way), splits each file into 500-token chunks, and asks a Large Language
+//! Model (LLM) (via an OpenAI-compatible endpoint) to **sanitize** them.
 //!
-//! The sanitized chunks are appended into YAML files named after the corresponding
-//! input `.txt` file. Each run produces YAML like:
+//! The sanitized chunks are written into YAML files (via `serde_yaml`, see
+//! [`yaml::Chunks`]) that mirror the input directory's tree under `output_dir`,
+//! named after the corresponding input file. Each run produces YAML like:
 //!
 //! ```yaml
 //! chunks:
-//!   - |-
-//!     Cleaned text line 1
-//!     Cleaned text line 2
+//! - |-
+//!   Cleaned text line 1
+//!   Cleaned text line 2
+//! total_chunks: 1
 //! ```
 //!
+//! Run the `verify` subcommand to round-trip every produced YAML file through
+//! the parser and confirm its chunk count matches its source.
+//!
+//! Run the `serve` subcommand to expose the same pipeline as a long-running
+//! HTTP service (see [`serve::run`]) instead of a one-shot directory crawl,
+//! so editors or ingestion pipelines can sanitize text on demand without
+//! shelling out per file.
+//!
 //! ## Multi-endpoint concurrency
 //!
 //! You can specify multiple configuration files (`--config` flags), each of which
 //! points to a separate LLM backend (e.g., a local instance, a cloud endpoint).
-//! The tool spawns **one worker thread per configuration file**, allowing multiple
-//! sanitizers to run concurrently across different endpoints.
+//! Rather than statically splitting files across one worker per config, every
+//! endpoint is folded into a shared [`pool::EndpointPool`] that pulls chunks off
+//! one work queue, so a slow or dead endpoint no longer stalls its own partition
+//! while the rest of the pool sits idle. An endpoint that exhausts its retries on
+//! a chunk is put into cooldown and the chunk is requeued for a different one.
 //!
 //! ## Example
 //! ```bash
@@ -33,10 +48,14 @@
 //!
 //! This will:
 //! - Load text files from `/path/to/input`.
-//! - Spawn two threads, one using `llama.yaml`, the other `colab.yaml`.
+//! - Feed chunks from every input file to a pool backed by `llama.yaml` and `colab.yaml`.
 //! - Write output YAMLs under `/path/to/output`.
 
+use std::collections::VecDeque;
+use std::net::SocketAddr;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex as StdMutex};
 use std::{fs, time::Duration};
 
 use awful_aj::{
@@ -44,35 +63,124 @@ use awful_aj::{
     config::AwfulJadeConfig,
     template::{self, ChatTemplate},
 };
-use clap::{Parser, command};
+use clap::{Parser, Subcommand, command};
 use serde::{Deserialize, Serialize};
 use std::error::Error;
-use std::io::Write as IoWrite;
 use text_splitter::{ChunkConfig, TextSplitter};
 use tiktoken_rs::cl100k_base;
-use tokio::task::spawn_blocking;
+use tokio::sync::Notify;
 use tokio::time::sleep;
 
+mod cache;
+mod crawl;
+mod diff;
+mod pool;
+mod serve;
+mod yaml;
+
+use cache::ChunkCache;
+use pool::EndpointPool;
+
 /// Command-line arguments for `awful_book_sanitizer`.
+///
+/// With no subcommand, runs the batch sanitization pipeline. See [`Command`]
+/// for other modes.
 #[derive(Parser, Debug)]
 #[command(name = "awful_book_sanitizer")]
 #[command(about = "Clean up excerpts from books formatted as txt", long_about = None)]
 struct Args {
-    /// Path to directory of `.txt` files to sanitize.
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Path to directory of input files to sanitize. Required unless a subcommand is given.
     #[arg(short, long = "input")]
-    input_dir: PathBuf,
+    input_dir: Option<PathBuf>,
 
-    /// Path to directory where `.yaml` files will be written.
+    /// Path to directory where `.yaml` files will be written. Required unless a subcommand is given.
     #[arg(short, long = "output")]
-    output_dir: PathBuf,
+    output_dir: Option<PathBuf>,
 
     /// One or more configuration files specifying API endpoints.
     ///
-    /// Each file is parsed into an [`AwfulJadeConfig`] and run in its own worker.
+    /// Each file is parsed into an [`AwfulJadeConfig`] and added to the shared
+    /// [`pool::EndpointPool`] that chunks are dispatched across.
     #[arg(long = "config", num_args = 1..)]
     config: Vec<PathBuf>,
+
+    /// Comma-separated list of file extensions to sanitize (without the leading dot).
+    ///
+    /// `input_dir` is walked recursively and every file whose extension is in this
+    /// set is picked up, honoring `.gitignore`/`.ignore` rules and hidden-file
+    /// conventions along the way.
+    #[arg(long = "extensions", value_delimiter = ',', default_value = "txt")]
+    extensions: Vec<String>,
+
+    /// Disable the on-disk chunk cache, forcing every chunk to hit the model.
+    #[arg(long = "no-cache")]
+    no_cache: bool,
+
+    /// Directory used to store cached sanitized chunks, keyed by content hash.
+    #[arg(long = "cache-dir", default_value = "cache")]
+    cache_dir: PathBuf,
+
+    /// Also emit a `.diff` file per input, showing a unified diff between
+    /// each chunk's original and sanitized text.
+    ///
+    /// This lets reviewers audit hallucinated insertions or accidental
+    /// deletions before trusting the cleaned corpus.
+    #[arg(long = "diff")]
+    diff: bool,
 }
 
+/// Subcommands offering alternatives to the default batch sanitization run.
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Round-trip every produced YAML file through the parser and report
+    /// any that fail to deserialize or whose chunk count doesn't match a
+    /// fresh split of their source file.
+    Verify {
+        /// Path to directory of original input files.
+        #[arg(short, long = "input")]
+        input_dir: PathBuf,
+
+        /// Path to directory of produced `.yaml` files to verify.
+        #[arg(short, long = "output")]
+        output_dir: PathBuf,
+    },
+
+    /// Run an HTTP service exposing the sanitization pipeline on demand,
+    /// instead of walking an input directory once and exiting.
+    ///
+    /// `POST /sanitize` splits the request body into chunks and sanitizes
+    /// them through the same chunking, template, and retry logic as the
+    /// batch pipeline, returning the sanitized chunks as JSON. `GET /health`
+    /// reports which configured endpoints are currently reachable.
+    Serve {
+        /// Address to bind the HTTP server to.
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        addr: SocketAddr,
+
+        /// One or more configuration files specifying API endpoints.
+        #[arg(long = "config", num_args = 1..)]
+        config: Vec<PathBuf>,
+
+        /// Disable the on-disk chunk cache, forcing every chunk to hit the model.
+        #[arg(long = "no-cache")]
+        no_cache: bool,
+
+        /// Directory used to store cached sanitized chunks, keyed by content hash.
+        #[arg(long = "cache-dir", default_value = "cache")]
+        cache_dir: PathBuf,
+    },
+}
+
+/// Name of the sanitization template, used both to load it and as part of
+/// the cache key. Note this only busts the cache on a template *rename*:
+/// [`ChunkCache::key_for`] hashes this name plus the chunk text, not the
+/// template's prompt body, so editing `book_txt_sanitizer`'s prompt while
+/// keeping its name will silently reuse chunks cached under the old prompt.
+pub(crate) const TEMPLATE_NAME: &str = "book_txt_sanitizer";
+
 /// Data structure for sanitized book excerpts, returned by the model.
 ///
 /// Each LLM response is expected to be valid JSON with this shape.
@@ -82,10 +190,11 @@ pub struct BookChunk {
     pub sanitizedBookExcerpt: String,
 }
 
-/// Entry point: parses arguments, spawns worker tasks, and drives sanitization.
+/// Entry point: parses arguments and either runs `verify`, runs `serve`, or
+/// builds the shared endpoint pool and drives sanitization.
 ///
-/// For each `--config` file, a separate blocking worker thread is spawned, running
-/// [`process_files`]. All workers run in parallel.
+/// Every `--config` file is loaded into one [`EndpointPool`]; [`process_files`]
+/// then dispatches chunks from every discovered input file across that pool.
 ///
 /// Returns `Ok(())` on success; prints errors to stderr otherwise.
 #[tokio::main]
@@ -93,71 +202,134 @@ async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
     // Parse command-line arguments
     let args = Args::parse();
 
-    // Clone paths to avoid moving them during spawning
-    let input_dir_path = args.input_dir.clone();
-    let output_dir_path = args.output_dir.to_str().unwrap().to_string();
+    match args.command {
+        Some(Command::Verify {
+            input_dir,
+            output_dir,
+        }) => {
+            return yaml::verify(&input_dir, &output_dir)
+                .await
+                .map_err(|e| -> Box<dyn Error + Send + Sync> { e.into() });
+        }
+        Some(Command::Serve {
+            addr,
+            config,
+            no_cache,
+            cache_dir,
+        }) => {
+            let mut configs = Vec::new();
+            for config_path in &config {
+                configs.push(
+                    awful_aj::config::load_config(config_path.to_str().unwrap())
+                        .map_err(|e| format!("Config load error: {e}"))?,
+                );
+            }
+            let pool = Arc::new(EndpointPool::new(configs));
+            let cache = Arc::new(ChunkCache::new(cache_dir, !no_cache));
+
+            return serve::run(addr, pool, cache)
+                .await
+                .map_err(|e| -> Box<dyn Error + Send + Sync> { e.into() });
+        }
+        None => {}
+    }
 
-    // Spawn tasks for each configuration file
-    let mut handles = Vec::new();
+    let input_dir = args
+        .input_dir
+        .ok_or("--input is required when no subcommand is given")?;
+    let output_dir = args
+        .output_dir
+        .ok_or("--output is required when no subcommand is given")?;
 
+    // Load every configured endpoint into the shared pool
+    let mut configs = Vec::new();
     for config_path in &args.config {
-        // Load configuration from file
-        let config = awful_aj::config::load_config(config_path.to_str().unwrap())
-            .map_err(|e| format!("Config load error: {e}"))?;
-
-        // Clone paths for safe use in spawned tasks
-        let input_clone = input_dir_path.clone();
-        let output_clone = output_dir_path.clone();
-
-        // Spawn a blocking task to process files
-        handles.push(spawn_blocking(move || {
-            tokio::runtime::Handle::current().block_on(async move {
-                process_files(&input_clone, &output_clone, config)
-                    .await
-                    .map_err(|e| -> Box<dyn Error + Send + Sync> { e.into() })
-            })
-        }));
+        configs.push(
+            awful_aj::config::load_config(config_path.to_str().unwrap())
+                .map_err(|e| format!("Config load error: {e}"))?,
+        );
     }
+    let pool = Arc::new(EndpointPool::new(configs));
+    let cache = Arc::new(ChunkCache::new(args.cache_dir.clone(), !args.no_cache));
+
+    process_files(
+        &input_dir,
+        &output_dir,
+        &args.extensions,
+        args.diff,
+        cache,
+        pool,
+    )
+    .await
+    .map_err(|e| -> Box<dyn Error + Send + Sync> { e.into() })
+}
 
-    // Wait for all tasks to complete
-    for handle in handles {
-        if let Err(e) = handle.await? {
-            eprintln!("Error in task: {}", e);
-        }
-    }
+/// A single chunk waiting to be sanitized, addressed by where its result
+/// belongs in the per-file results collected by [`process_files`].
+struct ChunkJob {
+    file_idx: usize,
+    chunk_idx: usize,
+    cache_key: String,
+    text: String,
+    /// How many more times this chunk may fail over to a different endpoint
+    /// before it's given up on. Starts at one less than the pool size, so a
+    /// chunk gets as many attempts as there are endpoints before being dropped.
+    hops_remaining: usize,
+}
 
-    Ok(())
+/// One discovered input file and the output paths its sanitized chunks (and,
+/// in `--diff` mode, their diffs) should be written to.
+struct FileJob {
+    source_path: String,
+    yaml_path: String,
+    diff_path: Option<String>,
 }
 
-/// Process `.txt` files under the given directory and sanitize their contents.
+/// Stand-in for a chunk left out of the sanitized output (an empty `"{}"`
+/// model response or one that exhausted every endpoint's failover) when
+/// building the `--diff` text, so its absence reads as a pipeline skip
+/// rather than as the model deleting that text.
+const SKIPPED_CHUNK_MARKER: &str = "[awful_book_sanitizer: chunk skipped (empty response or exhausted failover)]";
+
+/// Process files under the given directory and sanitize their contents.
 ///
-/// - Splits each file into ~500-token chunks.
-/// - Submits each chunk to the model using [`fetch_with_backoff`].
-/// - Appends sanitized chunks to a YAML file named after the input file.
+/// - Recursively discovers files matching `extensions` under `input_dir`,
+///   honoring `.gitignore`/`.ignore` rules (see [`crawl::discover_files`]).
+/// - Splits each file into ~500-token chunks and enqueues every cache-miss
+///   chunk onto a shared job queue.
+/// - Spawns one worker per endpoint in `pool`; workers pull jobs from the
+///   queue as they become free, so a fast endpoint naturally picks up more
+///   work than a slow one. A chunk that exhausts an endpoint's retries is
+///   requeued for a different endpoint (see [`pool::EndpointPool`]).
+/// - Writes sanitized chunks to a YAML file that mirrors the input file's
+///   position in the directory tree (see [`crawl::mirrored_output_path`]).
+/// - When `diff_mode` is set, also writes a `.diff` file per input with a
+///   unified diff of each chunk's original vs. sanitized text (see
+///   [`diff::unified_diff`]).
 ///
 /// # Parameters
-/// - `input_dir`: Path to directory containing `.txt` files.
-/// - `output_dir_path`: Path where YAML files are written.
-/// - `config`: Configuration for model endpoint.
+/// - `input_dir`: Path to directory containing book excerpts.
+/// - `output_dir`: Path where YAML files are written, mirroring `input_dir`'s layout.
+/// - `extensions`: File extensions (without leading dot) to include in the crawl.
+/// - `diff_mode`: Whether to additionally emit a `.diff` review file per input.
+/// - `cache`: Content-addressed cache of previously-sanitized chunks.
+/// - `pool`: Shared pool of LLM endpoints to dispatch chunks across.
 ///
 /// # Errors
-/// Returns `Err(String)` on filesystem, config, or API errors. Errors for
-/// individual files/chunks are logged and do not abort other files.
-///
-/// # Example
-/// ```no_run
-/// # async fn demo(cfg: awful_aj::config::AwfulJadeConfig) {
-/// let res = process_files(&"/tmp/books".into(), "/tmp/out", cfg).await;
-/// if let Err(err) = res {
-///     eprintln!("Sanitization failed: {err}");
-/// }
-/// # }
-/// ```
+/// Returns `Err(String)` on filesystem, config, or template errors. Failures
+/// dispatching an individual chunk are logged and do not abort other chunks.
 async fn process_files(
     input_dir: &PathBuf,
-    output_dir_path: &str,
-    config: AwfulJadeConfig,
+    output_dir: &PathBuf,
+    extensions: &[String],
+    diff_mode: bool,
+    cache: Arc<ChunkCache>,
+    pool: Arc<EndpointPool>,
 ) -> Result<(), String> {
+    if pool.is_empty() {
+        return Err("No endpoints configured; pass at least one --config".to_string());
+    }
+
     // Initialize tokenizer for tokenization
     let tokenizer = cl100k_base().map_err(|e| e.to_string())?;
     let max_tokens = 500;
@@ -165,89 +337,216 @@ async fn process_files(
     // Configure text splitter to chunk content
     let splitter = TextSplitter::new(ChunkConfig::new(max_tokens).with_sizer(tokenizer));
 
-    // Load template for sanitization
-    let template = template::load_template("book_txt_sanitizer")
-        .await
-        .map_err(|e| format!("Template load error: {e}"))?;
-
-    // Process each file in the input directory
-    for entry in fs::read_dir(input_dir).map_err(|e| e.to_string())? {
-        let entry = entry.map_err(|e| e.to_string())?;
-        let path = &entry.path();
-
-        // Check if the file is a `.txt` text file
-        if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("txt") {
-            let filename = path.file_name().unwrap().to_string_lossy();
-            let mut yaml_path = format!("{}/{}.yaml", output_dir_path, filename);
-
-            // Open YAML file for writing
-            let mut file = fs::OpenOptions::new()
-                .create(true)
-                .append(true)
-                .open(&yaml_path)
-                .map_err(|e| e.to_string())?;
-
-            // Write YAML header
-            writeln!(file, "chunks:").map_err(|e| e.to_string())?;
-
-            // Read and process the text content
-            let contents = fs::read_to_string(&path).map_err(|e| e.to_string())?;
-            let chunks: Vec<_> = splitter.chunks(&contents).collect();
-
-            // Process each chunk
-            for chunk in chunks {
-                let book_chunk = fetch_with_backoff(&config, &chunk, &template)
-                    .await
-                    .map_err(|e| e.to_string())?;
-
-                if let Some(sanitized_text) = book_chunk {
-                    // Write sanitized content to YAML
-                    write_row_to_file(sanitized_text, &mut yaml_path).map_err(|e| e.to_string())?;
+    // Load template for sanitization, shared read-only across every worker
+    let template = Arc::new(
+        template::load_template(TEMPLATE_NAME)
+            .await
+            .map_err(|e| format!("Template load error: {e}"))?,
+    );
+
+    // Recursively discover matching files, honoring .gitignore/.ignore rules
+    let paths = crawl::discover_files(input_dir, extensions)?;
+
+    let mut files = Vec::with_capacity(paths.len());
+    let mut originals: Vec<Vec<String>> = Vec::with_capacity(paths.len());
+    let mut results: Vec<Vec<Option<String>>> = Vec::with_capacity(paths.len());
+    let mut queue: VecDeque<ChunkJob> = VecDeque::new();
+
+    for (file_idx, path) in paths.iter().enumerate() {
+        let yaml_path_buf = crawl::mirrored_output_path(input_dir, output_dir, path)?;
+        if let Some(parent) = yaml_path_buf.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let yaml_path = yaml_path_buf.to_string_lossy().into_owned();
+
+        let diff_path = if diff_mode {
+            let diff_path_buf = crawl::mirrored_diff_path(input_dir, output_dir, path)?;
+            Some(diff_path_buf.to_string_lossy().into_owned())
+        } else {
+            None
+        };
+
+        let contents = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let chunks: Vec<String> = splitter.chunks(&contents).map(str::to_string).collect();
+
+        let mut file_originals = Vec::with_capacity(chunks.len());
+        let mut file_results = Vec::with_capacity(chunks.len());
+        for (chunk_idx, chunk) in chunks.into_iter().enumerate() {
+            let cache_key = ChunkCache::key_for(TEMPLATE_NAME, &chunk);
+            if let Some(cached) = cache.get(&cache_key) {
+                file_results.push(Some(cached));
+            } else {
+                file_results.push(None);
+                queue.push_back(ChunkJob {
+                    file_idx,
+                    chunk_idx,
+                    cache_key,
+                    text: chunk.clone(),
+                    hops_remaining: pool.len() - 1,
+                });
+            }
+            file_originals.push(chunk);
+        }
+
+        files.push(FileJob {
+            source_path: path.to_string_lossy().into_owned(),
+            yaml_path,
+            diff_path,
+        });
+        originals.push(file_originals);
+        results.push(file_results);
+    }
+
+    // Jobs still outstanding (queued or being retried); requeues don't change
+    // this count, only a terminal success or a fully-exhausted failover does.
+    let pending = Arc::new(AtomicUsize::new(queue.len()));
+    let queue = Arc::new(StdMutex::new(queue));
+    let results = Arc::new(StdMutex::new(results));
+    // Woken whenever a job is pushed onto `queue`, so an idle worker can wait
+    // for work instead of polling it on a fixed interval.
+    let job_available = Arc::new(Notify::new());
+
+    let mut workers = Vec::with_capacity(pool.len());
+    for worker_idx in 0..pool.len() {
+        workers.push(tokio::spawn(dispatch_worker(
+            worker_idx,
+            Arc::clone(&pool),
+            Arc::clone(&queue),
+            Arc::clone(&pending),
+            Arc::clone(&template),
+            Arc::clone(&cache),
+            Arc::clone(&results),
+            Arc::clone(&job_available),
+        )));
+    }
+    for worker in workers {
+        worker.await.map_err(|e| e.to_string())?;
+    }
+
+    // All chunks have resolved (or been given up on); write each file's YAML
+    // (and, in --diff mode, a unified diff against the original text).
+    let results = Arc::try_unwrap(results).unwrap().into_inner().unwrap();
+    for ((file, chunk_originals), chunks) in files.into_iter().zip(originals).zip(results) {
+        // Reassemble the whole file on both sides before diffing, rather than
+        // diffing chunk-by-chunk and concatenating the hunks: each chunk's
+        // hunks would otherwise renumber from line 1, producing an invalid
+        // diff of the file as a whole (see `diff::unified_diff`). Each chunk
+        // is ended with a newline so no two chunks merge into one line.
+        let mut original_text = String::new();
+        for chunk in &chunk_originals {
+            original_text.push_str(chunk);
+            if !chunk.ends_with('\n') {
+                original_text.push('\n');
+            }
+        }
+        let mut sanitized_chunks = Vec::with_capacity(chunks.len());
+        // Side text used only for the diff: a skipped chunk gets a marker
+        // line instead of being left out entirely, so `--diff` shows it as a
+        // pipeline skip rather than rendering it as the model deleting that
+        // text outright.
+        let mut sanitized_for_diff = String::new();
+        for sanitized_text in chunks {
+            match sanitized_text {
+                Some(sanitized_text) => {
+                    sanitized_for_diff.push_str(&sanitized_text);
+                    if !sanitized_text.ends_with('\n') {
+                        sanitized_for_diff.push('\n');
+                    }
+                    sanitized_chunks.push(sanitized_text);
+                }
+                None => {
+                    sanitized_for_diff.push_str(SKIPPED_CHUNK_MARKER);
+                    sanitized_for_diff.push('\n');
                 }
             }
         }
+
+        if let Some(diff_path) = &file.diff_path {
+            let diff_output = diff::unified_diff(
+                &file.source_path,
+                &original_text,
+                &file.yaml_path,
+                &sanitized_for_diff,
+            );
+            fs::write(diff_path, diff_output).map_err(|e| e.to_string())?;
+        }
+
+        yaml::write_chunks(&file.yaml_path, sanitized_chunks, chunk_originals.len())?;
     }
 
     Ok(())
 }
 
-/// Append a sanitized text chunk to an output YAML file.
-///
-/// Each chunk is written as:
-/// ```yaml
-///   - |-
-///     line 1
-///     line 2
-/// ```
-///
-/// # Parameters
-/// - `chunk`: Sanitized text to append.
-/// - `yaml_path`: Path to YAML file (modified by reference).
+/// Drive one endpoint's worker loop: pull jobs from the shared queue, dispatch
+/// them against `pool.endpoints[worker_idx]`, and either record a result or
+/// requeue the job for a different endpoint to pick up.
 ///
-/// # Errors
-/// Returns any I/O or formatting errors encountered.
-///
-/// # Example
-/// ```no_run
-/// let mut yaml_path = "/tmp/out/book.yaml".to_string();
-/// write_row_to_file("Cleaned text".into(), &mut yaml_path).unwrap();
-/// ```
-pub fn write_row_to_file(
-    chunk: String,
-    yaml_path: &mut String,
-) -> Result<(), Box<dyn Error + Send + Sync>> {
-    let mut file = fs::OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(&yaml_path)?; // Open YAML file for appending
-
-    // Write YAML line with content
-    writeln!(file, "\t- |-").map_err(|e| e.to_string())?;
-    for line in chunk.lines() {
-        writeln!(file, "\t\t{}", line).map_err(|e| e.to_string())?;
-    }
+/// Exits once the queue is empty and no jobs remain pending anywhere in the
+/// pool (`pending` reaches zero), meaning every chunk has either resolved
+/// successfully or exhausted its failovers.
+async fn dispatch_worker(
+    worker_idx: usize,
+    pool: Arc<EndpointPool>,
+    queue: Arc<StdMutex<VecDeque<ChunkJob>>>,
+    pending: Arc<AtomicUsize>,
+    template: Arc<ChatTemplate>,
+    cache: Arc<ChunkCache>,
+    results: Arc<StdMutex<Vec<Vec<Option<String>>>>>,
+    job_available: Arc<Notify>,
+) {
+    let config = &pool.endpoints[worker_idx].config;
+
+    loop {
+        if let Some(cooldown) = pool.cooldown_remaining(worker_idx) {
+            sleep(cooldown).await;
+        }
 
-    Ok(())
+        let job = { queue.lock().unwrap().pop_front() };
+        let job = match job {
+            Some(job) => job,
+            None => {
+                if pending.load(Ordering::SeqCst) == 0 {
+                    return;
+                }
+                // Another worker is holding the remaining job(s) or is in
+                // cooldown. Wait to be woken by a requeue rather than
+                // busy-polling; the timeout is just a backstop against a
+                // missed wakeup, not the expected wake path.
+                let _ = tokio::time::timeout(Duration::from_secs(1), job_available.notified()).await;
+                continue;
+            }
+        };
+
+        match fetch_with_backoff(config, &job.text, &template).await {
+            Ok(sanitized) => {
+                pool.report_success(worker_idx);
+                if let Some(text) = &sanitized {
+                    if let Err(e) = cache.put(&job.cache_key, text) {
+                        eprintln!("Failed to cache sanitized chunk: {e}");
+                    }
+                }
+                results.lock().unwrap()[job.file_idx][job.chunk_idx] = sanitized;
+                pending.fetch_sub(1, Ordering::SeqCst);
+            }
+            Err(e) => {
+                let cooldown = pool.report_failure(worker_idx);
+                eprintln!(
+                    "Endpoint {worker_idx} exhausted retries on a chunk ({e}); \
+                     cooling down for {cooldown:?}"
+                );
+                if job.hops_remaining == 0 {
+                    eprintln!("Chunk exhausted all endpoint failovers; dropping it");
+                    pending.fetch_sub(1, Ordering::SeqCst);
+                } else {
+                    queue.lock().unwrap().push_back(ChunkJob {
+                        hops_remaining: job.hops_remaining - 1,
+                        ..job
+                    });
+                    job_available.notify_one();
+                }
+            }
+        }
+    }
 }
 
 // The maximum number of times to retry a request to the LLM service.
@@ -278,7 +577,7 @@ const BASE_DELAY_MS: u64 = 500;
 /// }
 /// # }
 /// ```
-async fn fetch_with_backoff(
+pub(crate) async fn fetch_with_backoff(
     config: &AwfulJadeConfig,
     chunk: &str,
     template: &ChatTemplate,
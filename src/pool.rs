@@ -0,0 +1,105 @@
+//! Shared endpoint pool with load balancing and failover.
+//!
+//! Previously each `--config` got its own worker thread processing a fixed
+//! slice of files, so a slow or dead endpoint stalled its whole partition
+//! while the rest of the pool sat idle. [`EndpointPool`] instead tracks the
+//! health of every configured endpoint; workers pull jobs off a shared queue
+//! (see `main.rs`) rather than being statically assigned work, which means
+//! a faster or more available endpoint naturally picks up more jobs than a
+//! slow one — a simple, pull-based stand-in for least-outstanding-requests
+//! balancing. When an endpoint exhausts its retries on a chunk, the pool
+//! puts it into an exponentially growing cooldown so the dispatcher stops
+//! handing it work until it's likely to have recovered.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use awful_aj::config::AwfulJadeConfig;
+
+/// Initial cooldown applied the first time an endpoint exhausts its retries.
+const BASE_COOLDOWN: Duration = Duration::from_secs(5);
+/// Cap so a chronically-failing endpoint doesn't cool down for hours.
+const MAX_COOLDOWN: Duration = Duration::from_secs(5 * 60);
+
+/// Failure/cooldown state tracked for a single endpoint.
+struct Health {
+    consecutive_failures: u32,
+    unhealthy_until: Option<Instant>,
+}
+
+impl Health {
+    fn new() -> Self {
+        Self {
+            consecutive_failures: 0,
+            unhealthy_until: None,
+        }
+    }
+}
+
+/// One backend in the pool: its configuration plus failure/cooldown state.
+pub struct Endpoint {
+    pub config: AwfulJadeConfig,
+    health: Mutex<Health>,
+}
+
+/// A shared pool of LLM endpoints that chunks are dispatched across.
+pub struct EndpointPool {
+    pub endpoints: Vec<Endpoint>,
+}
+
+impl EndpointPool {
+    /// Build a pool from every loaded `--config` endpoint.
+    pub fn new(configs: Vec<AwfulJadeConfig>) -> Self {
+        Self {
+            endpoints: configs
+                .into_iter()
+                .map(|config| Endpoint {
+                    config,
+                    health: Mutex::new(Health::new()),
+                })
+                .collect(),
+        }
+    }
+
+    /// Number of endpoints in the pool.
+    pub fn len(&self) -> usize {
+        self.endpoints.len()
+    }
+
+    /// Whether the pool has no endpoints configured.
+    pub fn is_empty(&self) -> bool {
+        self.endpoints.is_empty()
+    }
+
+    /// Record that `index` exhausted [`crate::MAX_RETRIES`] on a chunk,
+    /// putting it into cooldown for an exponentially growing duration.
+    ///
+    /// Returns the cooldown the caller should wait out before trying this
+    /// endpoint again.
+    pub fn report_failure(&self, index: usize) -> Duration {
+        let mut health = self.endpoints[index].health.lock().unwrap();
+        health.consecutive_failures += 1;
+        let cooldown = BASE_COOLDOWN
+            .saturating_mul(2u32.saturating_pow(health.consecutive_failures - 1))
+            .min(MAX_COOLDOWN);
+        health.unhealthy_until = Some(Instant::now() + cooldown);
+        cooldown
+    }
+
+    /// Record a successful request against `index`, clearing any
+    /// accumulated failure state.
+    pub fn report_success(&self, index: usize) {
+        let mut health = self.endpoints[index].health.lock().unwrap();
+        health.consecutive_failures = 0;
+        health.unhealthy_until = None;
+    }
+
+    /// How much longer endpoint `index` should stay in cooldown, if at all.
+    pub fn cooldown_remaining(&self, index: usize) -> Option<Duration> {
+        let health = self.endpoints[index].health.lock().unwrap();
+        health.unhealthy_until.and_then(|until| {
+            let now = Instant::now();
+            (until > now).then(|| until - now)
+        })
+    }
+}
@@ -0,0 +1,206 @@
+//! HTTP service mode: sanitize text on demand instead of crawling a directory.
+//!
+//! `serve` exposes the same split -> [`fetch_with_backoff`] -> assemble
+//! pipeline the batch pipeline drives over a whole directory as a small HTTP
+//! API, so the sanitizer can be embedded in editors or ingestion pipelines
+//! without shelling out per file. `POST /sanitize` reuses the chunking,
+//! template loading, caching, and endpoint failover machinery the CLI
+//! already relies on; `GET /health` reports which configured endpoints are
+//! currently reachable.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use awful_aj::{
+    api::ask,
+    template::{self, ChatTemplate},
+};
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use text_splitter::{ChunkConfig, TextSplitter};
+use tiktoken_rs::cl100k_base;
+use tokio::time::timeout;
+
+use crate::{cache::ChunkCache, fetch_with_backoff, pool::EndpointPool, TEMPLATE_NAME};
+
+/// How long `GET /health` waits for a single response from an endpoint
+/// before declaring it unreachable. Unlike [`fetch_with_backoff`], a health
+/// probe makes one bounded attempt with no retry ladder, so a dead backend
+/// costs at most this long rather than the full backoff schedule.
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Shared state handed to every request handler.
+#[derive(Clone)]
+struct AppState {
+    pool: Arc<EndpointPool>,
+    cache: Arc<ChunkCache>,
+    template: Arc<ChatTemplate>,
+}
+
+/// `POST /sanitize` body when sent as `application/json`. Any other content
+/// type is treated as the raw text to sanitize directly.
+#[derive(Deserialize)]
+struct SanitizeRequest {
+    text: String,
+}
+
+/// `POST /sanitize` response: the sanitized chunks, in order.
+#[derive(Serialize)]
+struct SanitizeResponse {
+    chunks: Vec<String>,
+}
+
+/// Per-endpoint reachability as reported by `GET /health`.
+#[derive(Serialize)]
+struct EndpointHealth {
+    index: usize,
+    reachable: bool,
+}
+
+/// `GET /health` response body.
+#[derive(Serialize)]
+struct HealthResponse {
+    endpoints: Vec<EndpointHealth>,
+}
+
+/// Bind `addr` and serve `/sanitize` and `/health` until the process exits.
+///
+/// # Errors
+/// Returns `Err(String)` if the template can't be loaded or `addr` can't be
+/// bound.
+pub async fn run(addr: SocketAddr, pool: Arc<EndpointPool>, cache: Arc<ChunkCache>) -> Result<(), String> {
+    let template = Arc::new(
+        template::load_template(TEMPLATE_NAME)
+            .await
+            .map_err(|e| format!("Template load error: {e}"))?,
+    );
+
+    let state = AppState {
+        pool,
+        cache,
+        template,
+    };
+
+    let app = Router::new()
+        .route("/sanitize", post(sanitize_handler))
+        .route("/health", get(health_handler))
+        .with_state(state);
+
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .map_err(|e| e.to_string())?;
+    eprintln!("Listening on {addr}");
+    axum::serve(listener, app).await.map_err(|e| e.to_string())
+}
+
+/// Split the request body into chunks, sanitize each (via the cache or by
+/// dispatching to the pool with failover), and return them as JSON.
+async fn sanitize_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<Json<SanitizeResponse>, (StatusCode, String)> {
+    let is_json = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.starts_with("application/json"));
+
+    let text = if is_json {
+        serde_json::from_str::<SanitizeRequest>(&body)
+            .map_err(|e| (StatusCode::BAD_REQUEST, format!("invalid JSON body: {e}")))?
+            .text
+    } else {
+        body
+    };
+
+    let tokenizer = cl100k_base().map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let splitter = TextSplitter::new(ChunkConfig::new(500).with_sizer(tokenizer));
+    let chunks: Vec<String> = splitter.chunks(&text).map(str::to_string).collect();
+
+    let mut sanitized = Vec::with_capacity(chunks.len());
+    for chunk in chunks {
+        let cache_key = ChunkCache::key_for(TEMPLATE_NAME, &chunk);
+        if let Some(cached) = state.cache.get(&cache_key) {
+            sanitized.push(cached);
+            continue;
+        }
+
+        match dispatch_with_failover(&chunk, &cache_key, &state).await {
+            Ok(Some(text)) => sanitized.push(text),
+            Ok(None) => {}
+            Err(()) => {
+                return Err((
+                    StatusCode::BAD_GATEWAY,
+                    "every endpoint failed to sanitize this chunk".to_string(),
+                ));
+            }
+        }
+    }
+
+    Ok(Json(SanitizeResponse { chunks: sanitized }))
+}
+
+/// Try every endpoint in the pool, in order, skipping ones in cooldown,
+/// until one successfully sanitizes `chunk`. Mirrors the failover behavior
+/// of the batch pipeline's job queue, but for a single ad-hoc chunk rather
+/// than a shared backlog of jobs.
+async fn dispatch_with_failover(
+    chunk: &str,
+    cache_key: &str,
+    state: &AppState,
+) -> Result<Option<String>, ()> {
+    for (index, endpoint) in state.pool.endpoints.iter().enumerate() {
+        if state.pool.cooldown_remaining(index).is_some() {
+            continue;
+        }
+
+        match fetch_with_backoff(&endpoint.config, chunk, &state.template).await {
+            Ok(sanitized) => {
+                state.pool.report_success(index);
+                if let Some(text) = &sanitized {
+                    if let Err(e) = state.cache.put(cache_key, text) {
+                        eprintln!("Failed to cache sanitized chunk: {e}");
+                    }
+                }
+                return Ok(sanitized);
+            }
+            Err(e) => {
+                let cooldown = state.pool.report_failure(index);
+                eprintln!(
+                    "Endpoint {index} exhausted retries on a chunk ({e}); \
+                     cooling down for {cooldown:?}"
+                );
+            }
+        }
+    }
+
+    Err(())
+}
+
+/// Probe every configured endpoint with a single bounded request (no retry
+/// ladder) and report which ones responded within [`HEALTH_CHECK_TIMEOUT`].
+///
+/// Deliberately calls [`ask`] directly rather than [`fetch_with_backoff`]:
+/// the latter's `0..=MAX_RETRIES` backoff schedule would make a dead backend
+/// take upwards of fifteen seconds to report unreachable, and would bill a
+/// full model completion per healthy endpoint on every health check.
+async fn health_handler(State(state): State<AppState>) -> Json<HealthResponse> {
+    let mut endpoints = Vec::with_capacity(state.pool.endpoints.len());
+    for (index, endpoint) in state.pool.endpoints.iter().enumerate() {
+        let reachable = timeout(
+            HEALTH_CHECK_TIMEOUT,
+            ask(&endpoint.config, "ping".to_string(), &state.template, None, None),
+        )
+        .await
+        .is_ok_and(|res| res.is_ok());
+        endpoints.push(EndpointHealth { index, reachable });
+    }
+
+    Json(HealthResponse { endpoints })
+}
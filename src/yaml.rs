@@ -0,0 +1,120 @@
+//! Structured YAML emission and integrity verification for sanitized output.
+//!
+//! The sanitizer used to hand-roll its YAML with literal tab characters for
+//! block-scalar indentation, which the YAML spec forbids — the files were
+//! frequently unparseable by standard loaders. This module serializes
+//! sanitized chunks with `serde_yaml` instead, and exposes [`verify`] to
+//! round-trip every produced file through the parser and catch corruption
+//! or chunk-count drift before downstream consumers do.
+
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use text_splitter::{ChunkConfig, TextSplitter};
+use tiktoken_rs::cl100k_base;
+
+use crate::crawl;
+
+/// The on-disk shape of a sanitized output file: a flat list of chunks plus
+/// the number of chunks the source split into.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Chunks {
+    pub chunks: Vec<String>,
+    /// How many chunks the source text actually split into, including any
+    /// left out of `chunks` (an empty model response or one that exhausted
+    /// every endpoint's failover). `chunks.len()` alone can't distinguish a
+    /// legitimately short run from corruption, so [`verify`] checks this
+    /// field against a fresh split of the source instead.
+    pub total_chunks: usize,
+}
+
+/// Serialize `chunks` (out of `total_chunks` chunks the source split into)
+/// and write them to `yaml_path`, overwriting any previous contents.
+///
+/// # Errors
+/// Returns `Err(String)` if serialization or the write fails.
+pub fn write_chunks(yaml_path: &str, chunks: Vec<String>, total_chunks: usize) -> Result<(), String> {
+    let yaml =
+        serde_yaml::to_string(&Chunks { chunks, total_chunks }).map_err(|e| e.to_string())?;
+    fs::write(yaml_path, yaml).map_err(|e| e.to_string())
+}
+
+/// Parse a `.yaml` file previously written by [`write_chunks`].
+///
+/// # Errors
+/// Returns `Err(String)` if the file can't be read or doesn't deserialize
+/// into [`Chunks`].
+pub fn parse_chunks(yaml_path: &Path) -> Result<Chunks, String> {
+    let contents = fs::read_to_string(yaml_path).map_err(|e| e.to_string())?;
+    serde_yaml::from_str(&contents).map_err(|e| e.to_string())
+}
+
+/// Round-trip every `.yaml` file under `output_dir` through the parser, and
+/// cross-check its recorded [`Chunks::total_chunks`] against a fresh split
+/// of its source file under `input_dir`.
+///
+/// Prints one line per file (`OK <path>` or `FAIL <path>: <reason>`) and a
+/// summary count.
+///
+/// # Errors
+/// Returns `Err(String)` if `output_dir` can't be walked, or if any file
+/// fails to parse or has a mismatched chunk count.
+pub async fn verify(input_dir: &Path, output_dir: &Path) -> Result<(), String> {
+    let tokenizer = cl100k_base().map_err(|e| e.to_string())?;
+    let splitter = TextSplitter::new(ChunkConfig::new(500).with_sizer(tokenizer));
+
+    // Unlike source discovery, output discovery must not honor
+    // `.gitignore`/hidden-file rules: `output_dir` is routinely gitignored
+    // (or itself a hidden path), and an `ignore`-aware walk over it would
+    // silently find nothing to verify. See [`crawl::discover_output_files`].
+    let yaml_paths = crawl::discover_output_files(output_dir)?;
+
+    let mut failures = 0;
+    for yaml_path in &yaml_paths {
+        let outcome: Result<(), String> = (|| {
+            let parsed = parse_chunks(yaml_path)?;
+
+            let source_path = crawl::source_path_for_yaml(input_dir, output_dir, yaml_path)?;
+            let contents = fs::read_to_string(&source_path)
+                .map_err(|e| format!("could not read source {}: {e}", source_path.display()))?;
+            let expected = splitter.chunks(&contents).count();
+
+            if parsed.total_chunks != expected {
+                return Err(format!(
+                    "chunk count {} does not match source's {expected}",
+                    parsed.total_chunks
+                ));
+            }
+            if parsed.chunks.len() > parsed.total_chunks {
+                return Err(format!(
+                    "{} sanitized chunks exceeds the {} the source split into",
+                    parsed.chunks.len(),
+                    parsed.total_chunks
+                ));
+            }
+
+            Ok(())
+        })();
+
+        match outcome {
+            Ok(()) => println!("OK {}", yaml_path.display()),
+            Err(e) => {
+                eprintln!("FAIL {}: {e}", yaml_path.display());
+                failures += 1;
+            }
+        }
+    }
+
+    println!(
+        "{} of {} file(s) verified OK",
+        yaml_paths.len() - failures,
+        yaml_paths.len()
+    );
+
+    if failures > 0 {
+        Err(format!("{failures} file(s) failed verification"))
+    } else {
+        Ok(())
+    }
+}